@@ -1,9 +1,35 @@
-use clap::{App, Arg, SubCommand};
+use clap::{App, Arg, Shell, SubCommand};
+use std::io;
 use std::process::exit;
 
 fn main() {
+    let matches = cli().get_matches();
+    match matches.subcommand() {
+        ("set", Some(_matches)) => {
+            eprintln!("unimplemented");
+            exit(1);
+        }
+        ("get", Some(_matches)) => {
+            eprintln!("unimplemented");
+            exit(1);
+        }
+        ("rm", Some(_matches)) => {
+            eprintln!("unimplemented");
+            exit(1);
+        }
+        ("completions", Some(matches)) => {
+            // possible_values 已经校验过, 这里 parse 不会失败
+            let shell: Shell = matches.value_of("SHELL").unwrap().parse().unwrap();
+            cli().gen_completions_to(env!("CARGO_PKG_NAME"), shell, &mut io::stdout());
+        }
+        _ => unreachable!(),
+    };
+}
+
+/// 单独拆出来, 生成补全脚本的时候要再建一次 App
+fn cli() -> App<'static, 'static> {
     // 前几行这个 env! 都是为了初始化这个 App
-    let matches = App::new(env!("CARGO_PKG_NAME"))
+    App::new(env!("CARGO_PKG_NAME"))
         .version(env!("CARGO_PKG_VERSION"))
         .author(env!("CARGO_PKG_AUTHORS"))
         .about(env!("CARGO_PKG_DESCRIPTION"))
@@ -27,20 +53,21 @@ fn main() {
                 .about("Remove a given key")
                 .arg(Arg::with_name("KEY").help("A string key").required(true)),
         )
-        .get_matches();
-    match matches.subcommand() {
-        ("set", Some(_matches)) => {
-            eprintln!("unimplemented");
-            exit(1);
-        }
-        ("get", Some(_matches)) => {
-            eprintln!("unimplemented");
-            exit(1);
-        }
-        ("rm", Some(_matches)) => {
-            eprintln!("unimplemented");
-            exit(1);
-        }
-        _ => unreachable!(),
-    };
+        .subcommand(
+            SubCommand::with_name("completions")
+                .about("Print a shell completion script to stdout")
+                .arg(
+                    Arg::with_name("SHELL")
+                        .help("The shell to generate completions for")
+                        .possible_values(&["bash", "zsh", "fish", "powershell"])
+                        .required(true),
+                )
+                .after_help(
+                    "INSTALLING:\n    \
+                     bash:       kvs completions bash > ~/.local/share/bash-completion/completions/kvs\n    \
+                     zsh:        kvs completions zsh > ~/.zfunc/_kvs   (with ~/.zfunc in $fpath)\n    \
+                     fish:       kvs completions fish > ~/.config/fish/completions/kvs.fish\n    \
+                     powershell: kvs completions powershell >> $PROFILE",
+                ),
+        )
 }
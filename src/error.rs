@@ -0,0 +1,99 @@
+use std::error::Error;
+use std::fmt;
+use std::io;
+
+/// errors returned by the kv store
+#[derive(Debug)]
+pub enum KvsError {
+    /// the key does not exist, carries the missing key
+    KeyNotFound(String),
+    /// the stored value is not an integer
+    InvalidValueType,
+    /// an integer operation overflowed
+    Overflow,
+    /// reading or writing an export failed
+    Io(io::Error),
+    /// an export line is not valid json
+    Serde(serde_json::Error),
+    /// a scan pattern is not a valid regex
+    InvalidPattern(regex::Error),
+    /// a page size of zero was requested
+    InvalidLimit,
+}
+
+impl KvsError {
+    /// whether retrying the same operation may succeed
+    ///
+    /// | variant            | transient                                    |
+    /// |--------------------|----------------------------------------------|
+    /// | `KeyNotFound`      | no                                           |
+    /// | `InvalidValueType` | no                                           |
+    /// | `Overflow`         | no                                           |
+    /// | `Io`               | only for interrupted, timed out, would-block |
+    /// |                    | and connection-level failures                |
+    /// | `Serde`            | no                                           |
+    /// | `InvalidPattern`   | no                                           |
+    /// | `InvalidLimit`     | no                                           |
+    pub fn is_transient(&self) -> bool {
+        match self {
+            KvsError::Io(err) => matches!(
+                err.kind(),
+                io::ErrorKind::Interrupted
+                    | io::ErrorKind::WouldBlock
+                    | io::ErrorKind::TimedOut
+                    | io::ErrorKind::ConnectionRefused
+                    | io::ErrorKind::ConnectionReset
+                    | io::ErrorKind::ConnectionAborted
+                    | io::ErrorKind::NotConnected
+                    | io::ErrorKind::BrokenPipe
+            ),
+            _ => false,
+        }
+    }
+}
+
+impl fmt::Display for KvsError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            KvsError::KeyNotFound(key) => write!(f, "Key not found: {}", key),
+            KvsError::InvalidValueType => write!(f, "Value is not an integer"),
+            KvsError::Overflow => write!(f, "Integer overflow"),
+            KvsError::Io(err) => write!(f, "IO error: {}", err),
+            KvsError::Serde(err) => write!(f, "Serde error: {}", err),
+            KvsError::InvalidPattern(err) => write!(f, "Invalid pattern: {}", err),
+            KvsError::InvalidLimit => write!(f, "Page limit must be at least 1"),
+        }
+    }
+}
+
+impl Error for KvsError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            KvsError::Io(err) => Some(err),
+            KvsError::Serde(err) => Some(err),
+            KvsError::InvalidPattern(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for KvsError {
+    fn from(err: io::Error) -> KvsError {
+        KvsError::Io(err)
+    }
+}
+
+impl From<serde_json::Error> for KvsError {
+    fn from(err: serde_json::Error) -> KvsError {
+        KvsError::Serde(err)
+    }
+}
+
+impl From<regex::Error> for KvsError {
+    fn from(err: regex::Error) -> KvsError {
+        KvsError::InvalidPattern(err)
+    }
+}
+
+/// result type for kv store operations
+pub type Result<T> = std::result::Result<T, KvsError>;
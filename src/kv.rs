@@ -1,4 +1,10 @@
-use std::collections::HashMap;
+use crate::{CountMinSketch, KvsError, Result};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::ops::Bound;
+use std::time::{Duration, Instant};
 
 /// this doc test will NOT run unless under lib directory
 /// the kv store
@@ -10,28 +16,525 @@ use std::collections::HashMap;
 /// assert_eq!(val, Some("value".to_owned()));
 /// ```
 pub struct KvStore {
-    map: HashMap<String, String>,
+    map: BTreeMap<String, Entry>,
+    // approximate per-key access counts, only kept when the store was built
+    // with `with_hot_key_sketch`, see `top_k_hot_keys`
+    sketch: Option<CountMinSketch>,
+    // version handed to the most recent write, 0 means nothing written yet
+    last_version: u64,
+}
+
+/// one page of a `scan_page` walk
+#[derive(Debug)]
+pub struct ScanPage {
+    /// pairs in ascending key order
+    pub entries: Vec<(String, String)>,
+    /// pass back as `cursor` to fetch the next page, `None` once the scan is done
+    pub next_cursor: Option<String>,
+}
+
+/// a stored value, the version of the write that stored it and the moment it
+/// expires, if it was set with a ttl
+struct Entry {
+    value: String,
+    version: u64,
+    expires_at: Option<Instant>,
+}
+
+impl Entry {
+    fn new(value: String, version: u64) -> Entry {
+        Entry {
+            value,
+            version,
+            expires_at: None,
+        }
+    }
+
+    fn is_expired(&self) -> bool {
+        self.expires_at.is_some_and(|at| at <= Instant::now())
+    }
+
+    /// a copy of the value, `None` once expired
+    fn live_value(&self) -> Option<String> {
+        if self.is_expired() {
+            None
+        } else {
+            Some(self.value.clone())
+        }
+    }
+}
+
+/// one line of a json export
+#[derive(Serialize, Deserialize)]
+struct JsonEntry {
+    key: String,
+    value: String,
+    // remaining ttl at export time, absent for keys that never expire
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    expires_in_ms: Option<u64>,
+}
+
+/// turn a map entry into an owned pair, skipping expired ones
+fn live_pair((key, entry): (&String, &Entry)) -> Option<(String, String)> {
+    entry.live_value().map(|value| (key.clone(), value))
+}
+
+impl Default for KvStore {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl KvStore {
     pub fn new() -> KvStore {
         KvStore {
-            map: HashMap::new(),
+            map: BTreeMap::new(),
+            sketch: None,
+            last_version: 0,
+        }
+    }
+
+    /// create a store that also tracks approximate per-key access counts for
+    /// `top_k_hot_keys`, in a count-min sketch of `width` x `depth` counters
+    ///
+    /// every tracked access hashes the key `depth` times, so plain `new` skips
+    /// this; `CountMinSketch::DEFAULT_WIDTH` and `DEFAULT_DEPTH` (2048 x 5, 80 KB)
+    /// are a reasonable start
+    pub fn with_hot_key_sketch(width: usize, depth: usize) -> KvStore {
+        KvStore {
+            sketch: Some(CountMinSketch::new(width, depth)),
+            ..KvStore::new()
+        }
+    }
+
+    /// count one access of `key` when hot-key tracking is on
+    fn touch(&self, key: &str) {
+        if let Some(sketch) = &self.sketch {
+            sketch.increment(key);
         }
     }
 
+    fn next_version(&mut self) -> u64 {
+        self.last_version += 1;
+        self.last_version
+    }
+
     /// set a key value pair
     pub fn set(&mut self, key: String, value: String) {
-        self.map.insert(key, value);
+        self.touch(&key);
+        self.insert(key, value);
+    }
+
+    /// shared write path, does not count the access in the sketch
+    fn insert(&mut self, key: String, value: String) {
+        let version = self.next_version();
+        self.map.insert(key, Entry::new(value, version));
+    }
+
+    /// set a key value pair that stops being visible once `ttl` has passed
+    ///
+    /// expired keys are skipped by reads and dropped by the next write to them;
+    /// a ttl too large to represent, like `Duration::MAX`, never expires
+    pub fn set_with_ttl(&mut self, key: String, value: String, ttl: Duration) {
+        self.touch(&key);
+        let entry = Entry {
+            value,
+            version: self.next_version(),
+            expires_at: Instant::now().checked_add(ttl),
+        };
+        self.map.insert(key, entry);
+    }
+
+    /// set a key value pair only if the key is absent, returning whether it was set
+    pub fn set_if_absent(&mut self, key: String, value: String) -> bool {
+        self.touch(&key);
+        if self.contains_key(key.clone()) {
+            return false;
+        }
+        self.insert(key, value);
+        true
+    }
+
+    /// set a key value pair only if the key's current version is `expected_version`,
+    /// returning whether it was set
+    ///
+    /// an absent key has version 0
+    pub fn set_if_version(&mut self, key: String, value: String, expected_version: u64) -> bool {
+        self.touch(&key);
+        let current = self
+            .map
+            .get(&key)
+            .filter(|entry| !entry.is_expired())
+            .map_or(0, |entry| entry.version);
+        if current != expected_version {
+            return false;
+        }
+        self.insert(key, value);
+        true
+    }
+
+    /// set a batch of key value pairs, later pairs win on duplicate keys
+    pub fn set_many(&mut self, pairs: Vec<(String, String)>) {
+        for (key, value) in pairs {
+            self.set(key, value);
+        }
     }
 
     /// get value for a key
     pub fn get(&self, key: String) -> Option<String> {
-        self.map.get(&key).cloned()
+        self.lookup(&key)
+    }
+
+    /// shared read path, counts the access in the sketch
+    fn lookup(&self, key: &str) -> Option<String> {
+        self.touch(key);
+        self.map.get(key).and_then(Entry::live_value)
+    }
+
+    /// the `k` live keys with the highest estimated read and write counts, hottest first
+    ///
+    /// every call that reads or writes a key's value counts that key once, even
+    /// when it ends up changing nothing: `set*`, `get*`, `cas`, `incr`,
+    /// `import_from_json` and the destination of `rename`; `contains_key`,
+    /// removals, scans and the source of `rename` are not counted
+    ///
+    /// counts come from a count-min sketch, so they may overestimate but never
+    /// underestimate; this walks every key, so it is O(N log N); always empty
+    /// unless the store was built with `with_hot_key_sketch`
+    pub fn top_k_hot_keys(&self, k: usize) -> Vec<(String, u64)> {
+        let sketch = match &self.sketch {
+            Some(sketch) => sketch,
+            None => return Vec::new(),
+        };
+        let mut counts: Vec<(String, u64)> = self
+            .keys()
+            .map(|key| {
+                let count = sketch.estimate(&key);
+                (key, count)
+            })
+            .collect();
+        counts.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        counts.truncate(k);
+        counts
+    }
+
+    /// get value for a key together with its version
+    ///
+    /// versions come from a store-wide counter, so every write gives the key a
+    /// larger version than any it had before
+    pub fn get_versioned(&self, key: String) -> Option<(String, u64)> {
+        self.touch(&key);
+        let entry = self.map.get(&key)?;
+        entry.live_value().map(|value| (value, entry.version))
+    }
+
+    /// number of live keys in the store
+    ///
+    /// expired keys are skipped, which means checking every entry, so this is
+    /// O(N); call `remove_expired` first if they pile up
+    pub fn len(&self) -> usize {
+        self.map
+            .values()
+            .filter(|entry| !entry.is_expired())
+            .count()
+    }
+
+    /// whether the store holds no live keys
+    ///
+    /// stops at the first live key, but is O(N) when most keys have expired
+    pub fn is_empty(&self) -> bool {
+        self.map.values().all(Entry::is_expired)
+    }
+
+    /// check whether a key exists without cloning its value
+    pub fn contains_key(&self, key: String) -> bool {
+        self.map.get(&key).is_some_and(|entry| !entry.is_expired())
+    }
+
+    /// get values for a batch of keys, in the same order as `keys`
+    ///
+    /// missing keys map to `None`, an empty batch returns an empty vec
+    pub fn get_many(&self, keys: Vec<String>) -> Vec<Option<String>> {
+        keys.iter().map(|key| self.lookup(key)).collect()
+    }
+
+    /// iterate over all key value pairs in ascending key order
+    ///
+    /// the iterator borrows the store, so it can not be modified mid-iteration
+    pub fn iter(&self) -> impl Iterator<Item = (String, String)> + '_ {
+        self.map.iter().filter_map(live_pair)
+    }
+
+    /// iterate over all key value pairs in descending key order
+    pub fn iter_rev(&self) -> impl Iterator<Item = (String, String)> + '_ {
+        self.map.iter().rev().filter_map(live_pair)
+    }
+
+    /// iterate over all keys in ascending order without cloning any values
+    pub fn keys(&self) -> impl Iterator<Item = String> + '_ {
+        self.map
+            .iter()
+            .filter(|(_, entry)| !entry.is_expired())
+            .map(|(k, _)| k.clone())
+    }
+
+    /// iterate over keys that start with `prefix` in ascending order without cloning any values
+    pub fn keys_with_prefix<'a>(&'a self, prefix: &'a str) -> impl Iterator<Item = String> + 'a {
+        self.map
+            .range(prefix.to_owned()..)
+            .take_while(move |(k, _)| k.starts_with(prefix))
+            .filter(|(_, entry)| !entry.is_expired())
+            .map(|(k, _)| k.clone())
+    }
+
+    /// iterate over pairs whose key starts with `prefix`, in ascending key order
+    ///
+    /// seeks straight to the first candidate key, an empty prefix yields every pair
+    pub fn scan_prefix<'a>(
+        &'a self,
+        prefix: &'a str,
+    ) -> impl Iterator<Item = (String, String)> + 'a {
+        self.map
+            .range(prefix.to_owned()..)
+            .take_while(move |(k, _)| k.starts_with(prefix))
+            .filter_map(live_pair)
+    }
+
+    /// get up to `limit` pairs whose key matches the regex `pattern`, in ascending key order
+    ///
+    /// returns `KvsError::InvalidPattern` if the pattern does not compile; this
+    /// tests every key, so prefer `scan_prefix` when the keys share a known prefix
+    pub fn scan_regex(&self, pattern: &str, limit: Option<usize>) -> Result<Vec<(String, String)>> {
+        let re = Regex::new(pattern)?;
+        Ok(self
+            .map
+            .iter()
+            .filter(|(k, _)| re.is_match(k))
+            .filter_map(live_pair)
+            .take(limit.unwrap_or(usize::MAX))
+            .collect())
+    }
+
+    /// iterate over pairs whose key falls between `start` and `end`, in ascending key order
+    ///
+    /// an inverted or empty range yields nothing instead of panicking
+    pub fn scan_range(
+        &self,
+        start: Bound<String>,
+        end: Bound<String>,
+    ) -> impl Iterator<Item = (String, String)> + '_ {
+        let empty = match (&start, &end) {
+            (Bound::Included(s), Bound::Included(e)) => s > e,
+            (Bound::Included(s) | Bound::Excluded(s), Bound::Included(e) | Bound::Excluded(e)) => {
+                s >= e
+            }
+            _ => false,
+        };
+        let range = if empty {
+            None
+        } else {
+            Some(self.map.range((start, end)))
+        };
+        range.into_iter().flatten().filter_map(live_pair)
+    }
+
+    /// compare-and-swap: only write when the current value equals `expected`
+    ///
+    /// `expected = None` means the key must be absent, `new_value = None` removes
+    /// the key; returns whether the write happened
+    pub fn cas(
+        &mut self,
+        key: String,
+        expected: Option<String>,
+        new_value: Option<String>,
+    ) -> bool {
+        self.touch(&key);
+        if self.map.get(&key).and_then(Entry::live_value) != expected {
+            return false;
+        }
+        match new_value {
+            Some(value) => self.insert(key, value),
+            None => {
+                self.map.remove(&key);
+            }
+        }
+        true
+    }
+
+    /// add `delta` to the integer stored at `key`, treating an absent key as 0
+    ///
+    /// returns the new value, `KvsError::InvalidValueType` if the current value
+    /// is not an `i64`, or `KvsError::Overflow`; a ttl on the key is kept
+    pub fn incr(&mut self, key: String, delta: i64) -> Result<i64> {
+        self.touch(&key);
+        match self.map.get_mut(&key) {
+            Some(entry) if !entry.is_expired() => {
+                let current: i64 = entry
+                    .value
+                    .parse()
+                    .map_err(|_| KvsError::InvalidValueType)?;
+                let value = current.checked_add(delta).ok_or(KvsError::Overflow)?;
+                self.last_version += 1;
+                entry.value = value.to_string();
+                entry.version = self.last_version;
+                Ok(value)
+            }
+            _ => {
+                self.insert(key, delta.to_string());
+                Ok(delta)
+            }
+        }
+    }
+
+    /// move the value of `old_key` to `new_key`, overwriting any value already there
+    ///
+    /// returns `KvsError::KeyNotFound` if `old_key` is absent
+    pub fn rename(&mut self, old_key: String, new_key: String) -> Result<()> {
+        self.touch(&new_key);
+        match self.map.remove(&old_key) {
+            Some(mut entry) if !entry.is_expired() => {
+                entry.version = self.next_version();
+                self.map.insert(new_key, entry);
+                Ok(())
+            }
+            _ => Err(KvsError::KeyNotFound(old_key)),
+        }
+    }
+
+    /// get up to `limit` pairs with keys strictly after `cursor`, in ascending key order
+    ///
+    /// start with `cursor = None` and keep passing back `next_cursor` until it is `None`;
+    /// a `limit` of 0 could never make progress, so it is rejected with
+    /// `KvsError::InvalidLimit`
+    pub fn scan_page(&self, cursor: Option<String>, limit: usize) -> Result<ScanPage> {
+        if limit == 0 {
+            return Err(KvsError::InvalidLimit);
+        }
+        let start = match cursor {
+            Some(key) => Bound::Excluded(key),
+            None => Bound::Unbounded,
+        };
+        let mut entries: Vec<(String, String)> = self
+            .scan_range(start, Bound::Unbounded)
+            .take(limit.saturating_add(1))
+            .collect();
+        let has_more = entries.len() > limit;
+        entries.truncate(limit);
+        let next_cursor = if has_more {
+            entries.last().map(|(k, _)| k.clone())
+        } else {
+            None
+        };
+        Ok(ScanPage {
+            entries,
+            next_cursor,
+        })
     }
 
     /// remove value of a key
     pub fn remove(&mut self, key: String) {
         self.map.remove(&key);
     }
+
+    /// remove a batch of keys
+    ///
+    /// each entry in the result tells whether that key was present
+    pub fn remove_many(&mut self, keys: Vec<String>) -> Vec<bool> {
+        keys.iter()
+            .map(|key| {
+                self.map
+                    .remove(key)
+                    .is_some_and(|entry| !entry.is_expired())
+            })
+            .collect()
+    }
+
+    /// remove every key that starts with `prefix`, returning how many were removed
+    pub fn remove_prefix(&mut self, prefix: String) -> u64 {
+        let keys: Vec<String> = self
+            .map
+            .range(prefix.clone()..)
+            .take_while(|(k, _)| k.starts_with(&prefix))
+            .map(|(k, _)| k.clone())
+            .collect();
+        let mut removed = 0;
+        for key in keys {
+            if self
+                .map
+                .remove(&key)
+                .is_some_and(|entry| !entry.is_expired())
+            {
+                removed += 1;
+            }
+        }
+        removed
+    }
+
+    /// remove every key in the store, along with any hot-key counts
+    pub fn clear(&mut self) {
+        self.map.clear();
+        if let Some(sketch) = &self.sketch {
+            sketch.reset();
+        }
+    }
+
+    /// drop every expired key from the map, returning how many were dropped
+    pub fn remove_expired(&mut self) -> usize {
+        let before = self.map.len();
+        self.map.retain(|_, entry| !entry.is_expired());
+        before - self.map.len()
+    }
+
+    /// write every live pair as newline-delimited json `{"key":..,"value":..}`,
+    /// returning the number of pairs written
+    ///
+    /// keys set with a ttl also carry `"expires_in_ms"`, the time they had left
+    /// at export, so an import expires them at the same point
+    pub fn export_to_json(&self, writer: &mut impl Write) -> Result<u64> {
+        let now = Instant::now();
+        let mut count = 0;
+        for (key, entry) in &self.map {
+            let value = match entry.live_value() {
+                Some(value) => value,
+                None => continue,
+            };
+            let expires_in_ms = entry.expires_at.map(|at| {
+                u64::try_from(at.saturating_duration_since(now).as_millis()).unwrap_or(u64::MAX)
+            });
+            let json = JsonEntry {
+                key: key.clone(),
+                value,
+                expires_in_ms,
+            };
+            serde_json::to_writer(&mut *writer, &json)?;
+            writer.write_all(b"\n")?;
+            count += 1;
+        }
+        writer.flush()?;
+        Ok(count)
+    }
+
+    /// set every pair read from newline-delimited json written by `export_to_json`,
+    /// returning the number of pairs read
+    ///
+    /// blank lines are skipped; every line is parsed before anything is set, so a
+    /// read error or malformed line (`KvsError::Serde`) leaves the store unchanged
+    pub fn import_from_json(&mut self, reader: &mut impl Read) -> Result<u64> {
+        let mut entries = Vec::new();
+        for line in BufReader::new(reader).lines() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            entries.push(serde_json::from_str::<JsonEntry>(&line)?);
+        }
+        let count = entries.len() as u64;
+        for entry in entries {
+            match entry.expires_in_ms {
+                Some(ms) => self.set_with_ttl(entry.key, entry.value, Duration::from_millis(ms)),
+                None => self.set(entry.key, entry.value),
+            }
+        }
+        Ok(count)
+    }
 }
@@ -1,5 +1,11 @@
 /// pub use 一下数据结构
-pub use kv::KvStore;
+pub use error::{KvsError, Result};
+pub use kv::{KvStore, ScanPage};
+pub use sketch::CountMinSketch;
+pub use transaction::Transaction;
 
 /// mod 标记一下文件
+mod error;
 mod kv;
+mod sketch;
+mod transaction;
@@ -0,0 +1,70 @@
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// count-min sketch for approximate per-key access counts
+///
+/// estimates never undercount; with `n` total increments they overcount by
+/// at most `e / width * n` with probability `1 - e^-depth`
+pub struct CountMinSketch {
+    width: usize,
+    depth: usize,
+    // `depth` rows of `width` counters, laid out row after row
+    counters: Vec<AtomicU64>,
+}
+
+impl Default for CountMinSketch {
+    fn default() -> Self {
+        Self::new(Self::DEFAULT_WIDTH, Self::DEFAULT_DEPTH)
+    }
+}
+
+impl CountMinSketch {
+    /// default number of counters per row
+    pub const DEFAULT_WIDTH: usize = 2048;
+    /// default number of rows
+    pub const DEFAULT_DEPTH: usize = 5;
+
+    /// create an empty sketch, `width` and `depth` must be non-zero
+    pub fn new(width: usize, depth: usize) -> CountMinSketch {
+        assert!(
+            width > 0 && depth > 0,
+            "sketch width and depth must be non-zero"
+        );
+        CountMinSketch {
+            width,
+            depth,
+            counters: (0..width * depth).map(|_| AtomicU64::new(0)).collect(),
+        }
+    }
+
+    /// record one access of `key`
+    pub fn increment(&self, key: &str) {
+        for row in 0..self.depth {
+            self.counters[self.index(row, key)].fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    /// estimated number of accesses of `key`
+    pub fn estimate(&self, key: &str) -> u64 {
+        (0..self.depth)
+            .map(|row| self.counters[self.index(row, key)].load(Ordering::Relaxed))
+            .min()
+            .unwrap_or(0)
+    }
+
+    /// zero every counter
+    pub fn reset(&self) {
+        for counter in &self.counters {
+            counter.store(0, Ordering::Relaxed);
+        }
+    }
+
+    fn index(&self, row: usize, key: &str) -> usize {
+        // salt the hash with the row so each row spreads keys differently
+        let mut hasher = DefaultHasher::new();
+        row.hash(&mut hasher);
+        key.hash(&mut hasher);
+        row * self.width + (hasher.finish() % self.width as u64) as usize
+    }
+}
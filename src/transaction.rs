@@ -0,0 +1,63 @@
+use crate::KvStore;
+use std::collections::BTreeMap;
+
+/// a batch of writes that is applied to the store all at once on `commit`
+///
+/// the transaction holds the only mutable borrow of the store, so nobody else
+/// can write in between and no conflict check is needed
+/// ```rust
+/// # use kvs::{KvStore, Transaction};
+/// let mut store = KvStore::new();
+/// store.set("a".to_owned(), "10".to_owned());
+/// let mut txn = Transaction::begin(&mut store);
+/// txn.set("a".to_owned(), "7".to_owned());
+/// txn.set("b".to_owned(), "3".to_owned());
+/// txn.commit();
+/// assert_eq!(store.get("b".to_owned()), Some("3".to_owned()));
+/// ```
+pub struct Transaction<'a> {
+    store: &'a mut KvStore,
+    // `None` marks a buffered remove
+    writes: BTreeMap<String, Option<String>>,
+}
+
+impl<'a> Transaction<'a> {
+    /// start a transaction on the store
+    pub fn begin(store: &'a mut KvStore) -> Transaction<'a> {
+        Transaction {
+            store,
+            writes: BTreeMap::new(),
+        }
+    }
+
+    /// get value for a key, seeing this transaction's own buffered writes
+    pub fn get(&self, key: String) -> Option<String> {
+        match self.writes.get(&key) {
+            Some(write) => write.clone(),
+            None => self.store.get(key),
+        }
+    }
+
+    /// buffer a key value pair
+    pub fn set(&mut self, key: String, value: String) {
+        self.writes.insert(key, Some(value));
+    }
+
+    /// buffer the removal of a key
+    pub fn remove(&mut self, key: String) {
+        self.writes.insert(key, None);
+    }
+
+    /// apply every buffered write to the store
+    pub fn commit(self) {
+        for (key, write) in self.writes {
+            match write {
+                Some(value) => self.store.set(key, value),
+                None => self.store.remove(key),
+            }
+        }
+    }
+
+    /// drop every buffered write, leaving the store untouched
+    pub fn rollback(self) {}
+}
@@ -1,4 +1,5 @@
 use assert_cmd::prelude::*;
+use predicates::prelude::*;
 use predicates::str::contains;
 use std::process::Command;
 
@@ -13,7 +14,7 @@ fn cli_no_args() {
 fn cli_version() {
     Command::cargo_bin("kvs")
         .unwrap()
-        .args(&["-V"])
+        .args(["-V"])
         .assert()
         .stdout(contains(env!("CARGO_PKG_VERSION")));
 }
@@ -23,7 +24,7 @@ fn cli_version() {
 fn cli_get() {
     Command::cargo_bin("kvs")
         .unwrap()
-        .args(&["get", "key1"])
+        .args(["get", "key1"])
         .assert()
         .failure()
         .stderr(contains("unimplemented"));
@@ -34,7 +35,7 @@ fn cli_get() {
 fn cli_set() {
     Command::cargo_bin("kvs")
         .unwrap()
-        .args(&["get", "key1"])
+        .args(["get", "key1"])
         .assert()
         .failure()
         .stderr(contains("unimplemented"));
@@ -45,7 +46,7 @@ fn cli_set() {
 fn cli_rm() {
     Command::cargo_bin("kvs")
         .unwrap()
-        .args(&["rm", "key1"])
+        .args(["rm", "key1"])
         .assert()
         .failure()
         .stderr(contains("unimplemented"));
@@ -56,13 +57,13 @@ fn cli_invalid_get() {
     // get 缺参数
     Command::cargo_bin("kvs")
         .unwrap()
-        .args(&["get"])
+        .args(["get"])
         .assert()
         .failure();
     // 传了多余参数
     Command::cargo_bin("kvs")
         .unwrap()
-        .args(&["get", "extra", "field"])
+        .args(["get", "extra", "field"])
         .assert()
         .failure();
 }
@@ -71,17 +72,17 @@ fn cli_invalid_get() {
 fn cli_invalid_set() {
     Command::cargo_bin("kvs")
         .unwrap()
-        .args(&["set"])
+        .args(["set"])
         .assert()
         .failure();
     Command::cargo_bin("kvs")
         .unwrap()
-        .args(&["set", "missing field"])
+        .args(["set", "missing field"])
         .assert()
         .failure();
     Command::cargo_bin("kvs")
         .unwrap()
-        .args(&["set", "extra", "extra", "field"])
+        .args(["set", "extra", "extra", "field"])
         .assert()
         .failure();
 }
@@ -90,12 +91,12 @@ fn cli_invalid_set() {
 fn cli_invalid_rm() {
     Command::cargo_bin("kvs")
         .unwrap()
-        .args(&["rm"])
+        .args(["rm"])
         .assert()
         .failure();
     Command::cargo_bin("kvs")
         .unwrap()
-        .args(&["rm", "extra", "field"])
+        .args(["rm", "extra", "field"])
         .assert()
         .failure();
 }
@@ -104,7 +105,25 @@ fn cli_invalid_rm() {
 fn cli_invalid_subcommand() {
     Command::cargo_bin("kvs")
         .unwrap()
-        .args(&["unknown", "subcommand"])
+        .args(["unknown", "subcommand"])
+        .assert()
+        .failure();
+}
+
+// `kvs completions <SHELL>` should print a script mentioning every subcommand
+#[test]
+fn cli_completions() {
+    for shell in &["bash", "zsh", "fish", "powershell"] {
+        Command::cargo_bin("kvs")
+            .unwrap()
+            .args(["completions", shell])
+            .assert()
+            .success()
+            .stdout(contains("set").and(contains("get")).and(contains("rm")));
+    }
+    Command::cargo_bin("kvs")
+        .unwrap()
+        .args(["completions", "tcsh"])
         .assert()
         .failure();
 }
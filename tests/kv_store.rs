@@ -1,4 +1,8 @@
-use kvs::KvStore;
+use kvs::{CountMinSketch, KvStore, KvsError, Transaction};
+use std::io;
+use std::ops::Bound::{self, Excluded, Included, Unbounded};
+use std::thread;
+use std::time::Duration;
 
 /// 进行如下测试
 /// set key value
@@ -42,3 +46,670 @@ fn remove_key() {
     store.remove("key1".to_owned());
     assert_eq!(store.get("key1".to_owned()), None);
 }
+
+#[test]
+fn get_many_values() {
+    let mut store = KvStore::new();
+    store.set("key1".to_owned(), "value1".to_owned());
+    store.set("key2".to_owned(), "value2".to_owned());
+
+    let values = store.get_many(vec![
+        "key2".to_owned(),
+        "key3".to_owned(),
+        "key1".to_owned(),
+    ]);
+    assert_eq!(
+        values,
+        vec![Some("value2".to_owned()), None, Some("value1".to_owned())]
+    );
+    assert_eq!(store.get_many(vec![]), Vec::<Option<String>>::new());
+}
+
+#[test]
+fn set_many_values() {
+    let mut store = KvStore::new();
+    store.set("key1".to_owned(), "value1".to_owned());
+    store.set_many(vec![
+        ("key1".to_owned(), "value2".to_owned()),
+        ("key2".to_owned(), "value3".to_owned()),
+        ("key2".to_owned(), "value4".to_owned()),
+    ]);
+
+    assert_eq!(store.get("key1".to_owned()).unwrap(), "value2".to_owned());
+    assert_eq!(store.get("key2".to_owned()).unwrap(), "value4".to_owned());
+}
+
+#[test]
+fn remove_many_keys() {
+    let mut store = KvStore::new();
+    store.set("key1".to_owned(), "value1".to_owned());
+    store.set("key2".to_owned(), "value2".to_owned());
+
+    let removed = store.remove_many(vec![
+        "key1".to_owned(),
+        "key3".to_owned(),
+        "key2".to_owned(),
+    ]);
+    assert_eq!(removed, vec![true, false, true]);
+    assert_eq!(store.get("key1".to_owned()), None);
+    assert_eq!(store.get("key2".to_owned()), None);
+}
+
+#[test]
+fn iter_sorted_pairs() {
+    let mut store = KvStore::new();
+    store.set("key3".to_owned(), "value3".to_owned());
+    store.set("key1".to_owned(), "value1".to_owned());
+    store.set("key2".to_owned(), "value2".to_owned());
+    store.remove("key2".to_owned());
+
+    let pairs: Vec<(String, String)> = store.iter().collect();
+    assert_eq!(
+        pairs,
+        vec![
+            ("key1".to_owned(), "value1".to_owned()),
+            ("key3".to_owned(), "value3".to_owned()),
+        ]
+    );
+}
+
+#[test]
+fn scan_prefix_pairs() {
+    let mut store = KvStore::new();
+    store.set("user:1:name".to_owned(), "alice".to_owned());
+    store.set("user:2:name".to_owned(), "bob".to_owned());
+    store.set("user".to_owned(), "root".to_owned());
+    store.set("zone:1".to_owned(), "east".to_owned());
+
+    let keys: Vec<String> = store.scan_prefix("user:").map(|(k, _)| k).collect();
+    assert_eq!(
+        keys,
+        vec!["user:1:name".to_owned(), "user:2:name".to_owned()]
+    );
+    assert_eq!(store.scan_prefix("").count(), 4);
+    assert_eq!(store.scan_prefix("user:1:name:extra").count(), 0);
+}
+
+#[test]
+fn scan_range_bounds() {
+    let mut store = KvStore::new();
+    for day in ["2024-01-01", "2024-01-15", "2024-02-01", "2024-02-15"] {
+        store.set(day.to_owned(), "value".to_owned());
+    }
+    let keys = |start: Bound<&str>, end: Bound<&str>| -> Vec<String> {
+        store
+            .scan_range(start.map(str::to_owned), end.map(str::to_owned))
+            .map(|(k, _)| k)
+            .collect()
+    };
+
+    assert_eq!(
+        keys(Included("2024-01-01"), Included("2024-02-01")),
+        vec!["2024-01-01", "2024-01-15", "2024-02-01"]
+    );
+    assert_eq!(
+        keys(Included("2024-01-01"), Excluded("2024-02-01")),
+        vec!["2024-01-01", "2024-01-15"]
+    );
+    assert_eq!(
+        keys(Excluded("2024-01-01"), Included("2024-02-01")),
+        vec!["2024-01-15", "2024-02-01"]
+    );
+    assert_eq!(
+        keys(Excluded("2024-01-01"), Excluded("2024-02-01")),
+        vec!["2024-01-15"]
+    );
+    assert_eq!(keys(Unbounded, Excluded("2024-01-15")), vec!["2024-01-01"]);
+    assert_eq!(keys(Excluded("2024-02-01"), Unbounded), vec!["2024-02-15"]);
+    assert!(keys(Excluded("2024-01-15"), Excluded("2024-01-15")).is_empty());
+    assert!(keys(Included("2024-02-01"), Included("2024-01-01")).is_empty());
+}
+
+#[test]
+fn contains_key() {
+    let mut store = KvStore::new();
+    store.set("key1".to_owned(), "value1".to_owned());
+    assert!(store.contains_key("key1".to_owned()));
+    assert!(!store.contains_key("key2".to_owned()));
+
+    store.remove("key1".to_owned());
+    assert!(!store.contains_key("key1".to_owned()));
+}
+
+#[test]
+fn len_and_is_empty() {
+    let mut store = KvStore::new();
+    assert!(store.is_empty());
+    assert_eq!(store.len(), 0);
+
+    store.set("key1".to_owned(), "value1".to_owned());
+    store.set("key2".to_owned(), "value2".to_owned());
+    store.set("key1".to_owned(), "value3".to_owned());
+    assert_eq!(store.len(), 2);
+
+    store.remove("key1".to_owned());
+    assert_eq!(store.len(), 1);
+    assert!(!store.is_empty());
+}
+
+#[test]
+fn clear_store() {
+    let mut store = KvStore::new();
+    store.set("key1".to_owned(), "value1".to_owned());
+    store.set("key2".to_owned(), "value2".to_owned());
+
+    store.clear();
+    assert_eq!(store.len(), 0);
+    assert_eq!(store.get("key1".to_owned()), None);
+    assert_eq!(store.get("key2".to_owned()), None);
+}
+
+#[test]
+fn set_with_ttl_expires() {
+    let mut store = KvStore::new();
+    store.set_with_ttl(
+        "key1".to_owned(),
+        "value1".to_owned(),
+        Duration::from_millis(100),
+    );
+    store.set_with_ttl(
+        "key2".to_owned(),
+        "value2".to_owned(),
+        Duration::from_millis(100),
+    );
+    store.set("key2".to_owned(), "value3".to_owned());
+    store.set_with_ttl(
+        "key3".to_owned(),
+        "value4".to_owned(),
+        Duration::from_secs(60),
+    );
+    assert_eq!(store.get("key1".to_owned()).unwrap(), "value1".to_owned());
+
+    thread::sleep(Duration::from_millis(200));
+    assert_eq!(store.get("key1".to_owned()), None);
+    assert!(!store.contains_key("key1".to_owned()));
+    // a plain set clears an earlier ttl
+    assert_eq!(store.get("key2".to_owned()).unwrap(), "value3".to_owned());
+    assert_eq!(store.get("key3".to_owned()).unwrap(), "value4".to_owned());
+    let keys: Vec<String> = store.iter().map(|(k, _)| k).collect();
+    assert_eq!(keys, vec!["key2".to_owned(), "key3".to_owned()]);
+}
+
+#[test]
+fn len_skips_expired_keys() {
+    let mut store = KvStore::new();
+    store.set_with_ttl(
+        "key1".to_owned(),
+        "value1".to_owned(),
+        Duration::from_millis(100),
+    );
+    assert_eq!(store.len(), 1);
+    assert!(!store.is_empty());
+
+    thread::sleep(Duration::from_millis(200));
+    assert_eq!(store.len(), 0);
+    assert!(store.is_empty());
+}
+
+#[test]
+fn remove_expired_keys() {
+    let mut store = KvStore::new();
+    for i in 0..100 {
+        store.set_with_ttl(
+            format!("key{}", i),
+            format!("value{}", i),
+            Duration::from_millis(100),
+        );
+    }
+    store.set("key100".to_owned(), "value100".to_owned());
+    assert_eq!(store.remove_expired(), 0);
+    assert_eq!(store.len(), 101);
+
+    thread::sleep(Duration::from_millis(200));
+    assert_eq!(store.len(), 1);
+    assert_eq!(store.remove_expired(), 100);
+    assert_eq!(store.len(), 1);
+    assert_eq!(
+        store.get("key100".to_owned()).unwrap(),
+        "value100".to_owned()
+    );
+}
+
+#[test]
+fn cas_value() {
+    let mut store = KvStore::new();
+    // absent key only matches `None`
+    assert!(!store.cas(
+        "lock".to_owned(),
+        Some("a".to_owned()),
+        Some("b".to_owned())
+    ));
+    assert!(store.cas("lock".to_owned(), None, Some("owner1".to_owned())));
+    assert!(!store.cas("lock".to_owned(), None, Some("owner2".to_owned())));
+    assert_eq!(store.get("lock".to_owned()).unwrap(), "owner1".to_owned());
+
+    assert!(store.cas(
+        "lock".to_owned(),
+        Some("owner1".to_owned()),
+        Some("owner2".to_owned())
+    ));
+    assert_eq!(store.get("lock".to_owned()).unwrap(), "owner2".to_owned());
+
+    assert!(!store.cas("lock".to_owned(), Some("owner1".to_owned()), None));
+    assert!(store.cas("lock".to_owned(), Some("owner2".to_owned()), None));
+    assert_eq!(store.get("lock".to_owned()), None);
+}
+
+#[test]
+fn transaction_commit_and_rollback() {
+    let mut store = KvStore::new();
+    store.set("a".to_owned(), "10".to_owned());
+    store.set("b".to_owned(), "0".to_owned());
+
+    let mut txn = Transaction::begin(&mut store);
+    let a: i64 = txn.get("a".to_owned()).unwrap().parse().unwrap();
+    txn.set("a".to_owned(), (a - 3).to_string());
+    txn.set("b".to_owned(), "3".to_owned());
+    txn.remove("c".to_owned());
+    // reads inside the transaction see its own writes
+    assert_eq!(txn.get("a".to_owned()).unwrap(), "7".to_owned());
+    txn.commit();
+    assert_eq!(store.get("a".to_owned()).unwrap(), "7".to_owned());
+    assert_eq!(store.get("b".to_owned()).unwrap(), "3".to_owned());
+
+    let mut txn = Transaction::begin(&mut store);
+    txn.set("a".to_owned(), "0".to_owned());
+    txn.remove("b".to_owned());
+    assert_eq!(txn.get("b".to_owned()), None);
+    txn.rollback();
+    assert_eq!(store.get("a".to_owned()).unwrap(), "7".to_owned());
+    assert_eq!(store.get("b".to_owned()).unwrap(), "3".to_owned());
+}
+
+#[test]
+fn rename_key() {
+    let mut store = KvStore::new();
+    store.set("key1".to_owned(), "value1".to_owned());
+    store.set("key2".to_owned(), "value2".to_owned());
+
+    store.rename("key1".to_owned(), "key3".to_owned()).unwrap();
+    assert_eq!(store.get("key1".to_owned()), None);
+    assert_eq!(store.get("key3".to_owned()).unwrap(), "value1".to_owned());
+
+    // renaming onto an existing key overwrites it
+    store.rename("key3".to_owned(), "key2".to_owned()).unwrap();
+    assert_eq!(store.get("key2".to_owned()).unwrap(), "value1".to_owned());
+    assert_eq!(store.len(), 1);
+
+    let err = store
+        .rename("key1".to_owned(), "key4".to_owned())
+        .unwrap_err();
+    assert!(matches!(&err, KvsError::KeyNotFound(key) if key == "key1"));
+    assert_eq!(err.to_string(), "Key not found: key1");
+    assert_eq!(store.get("key4".to_owned()), None);
+}
+
+#[test]
+fn incr_value() {
+    let mut store = KvStore::new();
+    assert_eq!(store.incr("counter".to_owned(), 5).unwrap(), 5);
+    assert_eq!(store.incr("counter".to_owned(), -7).unwrap(), -2);
+    assert_eq!(store.get("counter".to_owned()).unwrap(), "-2".to_owned());
+
+    store.set("name".to_owned(), "alice".to_owned());
+    assert!(matches!(
+        store.incr("name".to_owned(), 1),
+        Err(KvsError::InvalidValueType)
+    ));
+    assert_eq!(store.get("name".to_owned()).unwrap(), "alice".to_owned());
+
+    store.set("max".to_owned(), i64::MAX.to_string());
+    assert!(matches!(
+        store.incr("max".to_owned(), 1),
+        Err(KvsError::Overflow)
+    ));
+}
+
+#[test]
+fn remove_prefix_keys() {
+    let mut store = KvStore::new();
+    store.set("session:1:user".to_owned(), "alice".to_owned());
+    store.set("session:1:token".to_owned(), "abc".to_owned());
+    store.set("session:2:user".to_owned(), "bob".to_owned());
+    store.set("session:1".to_owned(), "other".to_owned());
+
+    assert_eq!(store.remove_prefix("session:1:".to_owned()), 2);
+    assert_eq!(store.get("session:1:user".to_owned()), None);
+    assert_eq!(store.get("session:1:token".to_owned()), None);
+    assert_eq!(
+        store.get("session:2:user".to_owned()).unwrap(),
+        "bob".to_owned()
+    );
+    assert_eq!(
+        store.get("session:1".to_owned()).unwrap(),
+        "other".to_owned()
+    );
+
+    assert_eq!(store.remove_prefix("missing:".to_owned()), 0);
+    assert_eq!(store.len(), 2);
+}
+
+#[test]
+fn export_and_import_json() {
+    let mut store = KvStore::new();
+    store.set("key1".to_owned(), "value1".to_owned());
+    store.set("key2".to_owned(), "line\n\"quoted\"".to_owned());
+
+    let mut buf = Vec::new();
+    assert_eq!(store.export_to_json(&mut buf).unwrap(), 2);
+    assert_eq!(
+        String::from_utf8(buf.clone()).unwrap(),
+        "{\"key\":\"key1\",\"value\":\"value1\"}\n\
+         {\"key\":\"key2\",\"value\":\"line\\n\\\"quoted\\\"\"}\n"
+    );
+
+    let mut imported = KvStore::new();
+    assert_eq!(imported.import_from_json(&mut buf.as_slice()).unwrap(), 2);
+    assert_eq!(
+        imported.iter().collect::<Vec<_>>(),
+        store.iter().collect::<Vec<_>>()
+    );
+
+    let mut bad = "{\"key\":\"key3\"}\n".as_bytes();
+    assert!(matches!(
+        imported.import_from_json(&mut bad),
+        Err(KvsError::Serde(_))
+    ));
+}
+
+#[test]
+fn import_json_is_all_or_nothing() {
+    let mut store = KvStore::new();
+    store.set("a".to_owned(), "0".to_owned());
+
+    let mut input = "{\"key\":\"a\",\"value\":\"1\"}\n{\"key\":\"b\"}\n".as_bytes();
+    assert!(matches!(
+        store.import_from_json(&mut input),
+        Err(KvsError::Serde(_))
+    ));
+    assert_eq!(store.len(), 1);
+    assert_eq!(store.get("a".to_owned()).unwrap(), "0".to_owned());
+    assert_eq!(store.get("b".to_owned()), None);
+}
+
+#[test]
+fn export_and_import_json_keeps_ttl() {
+    let mut store = KvStore::new();
+    store.set_with_ttl(
+        "session".to_owned(),
+        "token".to_owned(),
+        Duration::from_millis(100),
+    );
+    store.set("user".to_owned(), "alice".to_owned());
+
+    let mut buf = Vec::new();
+    assert_eq!(store.export_to_json(&mut buf).unwrap(), 2);
+    let mut imported = KvStore::new();
+    assert_eq!(imported.import_from_json(&mut buf.as_slice()).unwrap(), 2);
+    assert_eq!(
+        imported.get("session".to_owned()).unwrap(),
+        "token".to_owned()
+    );
+
+    thread::sleep(Duration::from_millis(200));
+    assert_eq!(store.get("session".to_owned()), None);
+    assert_eq!(imported.get("session".to_owned()), None);
+    assert_eq!(imported.get("user".to_owned()).unwrap(), "alice".to_owned());
+}
+
+#[test]
+fn iter_rev_pairs() {
+    let mut store = KvStore::new();
+    for key in ["key2", "key4", "key1", "key3"] {
+        store.set(key.to_owned(), format!("{}-value", key));
+    }
+
+    let forward: Vec<(String, String)> = store.iter().collect();
+    let mut backward: Vec<(String, String)> = store.iter_rev().collect();
+    assert_eq!(backward.first(), forward.last());
+    assert_eq!(backward[0].0, "key4".to_owned());
+    backward.reverse();
+    assert_eq!(backward, forward);
+}
+
+#[test]
+fn scan_page_walks_every_key_once() {
+    let mut store = KvStore::new();
+    for i in 0..10000 {
+        store.set(format!("key{:05}", i), format!("value{}", i));
+    }
+
+    let mut keys = Vec::new();
+    let mut cursor = None;
+    loop {
+        let page = store.scan_page(cursor, 300).unwrap();
+        assert!(page.entries.len() <= 300);
+        keys.extend(page.entries.into_iter().map(|(k, _)| k));
+        cursor = page.next_cursor;
+        if cursor.is_none() {
+            break;
+        }
+    }
+    let expected: Vec<String> = (0..10000).map(|i| format!("key{:05}", i)).collect();
+    assert_eq!(keys, expected);
+
+    // an exactly full last page does not hand out a dangling cursor
+    let page = store.scan_page(Some("key09899".to_owned()), 100).unwrap();
+    assert_eq!(page.entries.len(), 100);
+    assert_eq!(page.next_cursor, None);
+
+    // an unbounded limit returns everything in one page
+    let page = store.scan_page(None, usize::MAX).unwrap();
+    assert_eq!(page.entries.len(), 10000);
+    assert_eq!(page.next_cursor, None);
+
+    // a zero limit would never advance, so it is rejected
+    assert!(matches!(
+        store.scan_page(None, 0),
+        Err(KvsError::InvalidLimit)
+    ));
+    assert!(matches!(
+        store.scan_page(Some("key00000".to_owned()), 0),
+        Err(KvsError::InvalidLimit)
+    ));
+}
+
+#[test]
+fn list_keys() {
+    let mut store = KvStore::new();
+    store.set("user:2".to_owned(), "bob".to_owned());
+    store.set("user:1".to_owned(), "alice".to_owned());
+    store.set("user:3".to_owned(), "carol".to_owned());
+    store.set("group:1".to_owned(), "admins".to_owned());
+    store.remove("user:3".to_owned());
+    store.set("user:1".to_owned(), "alice2".to_owned());
+
+    assert_eq!(
+        store.keys().collect::<Vec<_>>(),
+        vec!["group:1", "user:1", "user:2"]
+    );
+    assert_eq!(
+        store.keys_with_prefix("user:").collect::<Vec<_>>(),
+        vec!["user:1", "user:2"]
+    );
+    assert_eq!(store.keys_with_prefix("none:").count(), 0);
+}
+
+#[test]
+fn top_k_hot_keys() {
+    let mut store =
+        KvStore::with_hot_key_sketch(CountMinSketch::DEFAULT_WIDTH, CountMinSketch::DEFAULT_DEPTH);
+    for i in 0..1000 {
+        store.set(format!("key{}", i), "value".to_owned());
+    }
+    // key0 is read 500 times, key1 400 times, key2 300 times, the rest never
+    for (key, reads) in [("key0", 500), ("key1", 400), ("key2", 300)] {
+        for _ in 0..reads {
+            store.get(key.to_owned());
+        }
+    }
+
+    let hot = store.top_k_hot_keys(3);
+    let keys: Vec<&str> = hot.iter().map(|(k, _)| k.as_str()).collect();
+    assert_eq!(keys, vec!["key0", "key1", "key2"]);
+    // 2200 increments in total, so each estimate is within e / 2048 * 2200 < 3
+    for ((_, estimate), actual) in hot.iter().zip([501, 401, 301]) {
+        assert!(*estimate >= actual);
+        assert!(*estimate <= actual + 3);
+    }
+    assert!(store.top_k_hot_keys(0).is_empty());
+
+    // tracking is opt-in, a plain store reports nothing
+    let mut plain = KvStore::new();
+    plain.set("key0".to_owned(), "value".to_owned());
+    plain.get("key0".to_owned());
+    assert!(plain.top_k_hot_keys(1).is_empty());
+}
+
+#[test]
+fn top_k_hot_keys_counts_every_path() {
+    let mut store =
+        KvStore::with_hot_key_sketch(CountMinSketch::DEFAULT_WIDTH, CountMinSketch::DEFAULT_DEPTH);
+    for _ in 0..10 {
+        store.set_with_ttl("a".to_owned(), "1".to_owned(), Duration::from_secs(60));
+    }
+    store.get_many(vec!["a".to_owned(); 10]);
+    assert_eq!(store.top_k_hot_keys(1), vec![("a".to_owned(), 20)]);
+
+    for _ in 0..5 {
+        store.incr("a".to_owned(), 1).unwrap();
+    }
+    assert_eq!(store.top_k_hot_keys(1), vec![("a".to_owned(), 25)]);
+
+    store.rename("a".to_owned(), "b".to_owned()).unwrap();
+    assert_eq!(store.top_k_hot_keys(1), vec![("b".to_owned(), 1)]);
+}
+
+#[test]
+fn top_k_hot_keys_counts_each_call_once() {
+    let mut store =
+        KvStore::with_hot_key_sketch(CountMinSketch::DEFAULT_WIDTH, CountMinSketch::DEFAULT_DEPTH);
+    assert!(store.cas("a".to_owned(), None, Some("1".to_owned())));
+    assert!(!store.cas("a".to_owned(), None, Some("2".to_owned())));
+    assert!(!store.set_if_absent("a".to_owned(), "3".to_owned()));
+    assert!(store.set_if_version("a".to_owned(), "4".to_owned(), 1));
+    assert_eq!(store.incr("b".to_owned(), 1).unwrap(), 1);
+    assert_eq!(
+        store.top_k_hot_keys(2),
+        vec![("a".to_owned(), 4), ("b".to_owned(), 1)]
+    );
+
+    assert!(store.contains_key("a".to_owned()));
+    assert_eq!(store.scan_prefix("a").count(), 1);
+    store.remove("b".to_owned());
+    assert_eq!(store.top_k_hot_keys(1), vec![("a".to_owned(), 4)]);
+}
+
+#[test]
+fn clear_resets_hot_keys() {
+    let mut store =
+        KvStore::with_hot_key_sketch(CountMinSketch::DEFAULT_WIDTH, CountMinSketch::DEFAULT_DEPTH);
+    for _ in 0..10 {
+        store.set("a".to_owned(), "1".to_owned());
+        store.set("b".to_owned(), "2".to_owned());
+    }
+    store.clear();
+
+    store.set("a".to_owned(), "1".to_owned());
+    store.set("c".to_owned(), "3".to_owned());
+    assert_eq!(
+        store.top_k_hot_keys(2),
+        vec![("a".to_owned(), 1), ("c".to_owned(), 1)]
+    );
+}
+
+#[test]
+fn set_if_absent() {
+    let mut store = KvStore::new();
+    assert!(store.set_if_absent("lock".to_owned(), "owner1".to_owned()));
+    assert!(!store.set_if_absent("lock".to_owned(), "owner2".to_owned()));
+    assert_eq!(store.get("lock".to_owned()).unwrap(), "owner1".to_owned());
+
+    store.remove("lock".to_owned());
+    assert!(store.set_if_absent("lock".to_owned(), "owner2".to_owned()));
+    assert_eq!(store.get("lock".to_owned()).unwrap(), "owner2".to_owned());
+}
+
+#[test]
+fn set_if_version() {
+    let mut store = KvStore::new();
+    assert_eq!(store.get_versioned("key1".to_owned()), None);
+    // an absent key has version 0
+    assert!(store.set_if_version("key1".to_owned(), "value1".to_owned(), 0));
+    let (value, version) = store.get_versioned("key1".to_owned()).unwrap();
+    assert_eq!(value, "value1".to_owned());
+
+    assert!(!store.set_if_version("key1".to_owned(), "value2".to_owned(), 0));
+    assert!(store.set_if_version("key1".to_owned(), "value2".to_owned(), version));
+    let (value, new_version) = store.get_versioned("key1".to_owned()).unwrap();
+    assert_eq!(value, "value2".to_owned());
+    assert!(new_version > version);
+    // a stale version no longer matches
+    assert!(!store.set_if_version("key1".to_owned(), "value3".to_owned(), version));
+
+    store.set("key1".to_owned(), "value4".to_owned());
+    assert!(store.get_versioned("key1".to_owned()).unwrap().1 > new_version);
+}
+
+#[test]
+fn scan_regex_pairs() {
+    let mut store = KvStore::new();
+    for i in 0..10000 {
+        store.set(
+            format!("user:{}:email", i),
+            format!("user{}@example.com", i),
+        );
+        store.set(format!("user:{}:name", i), format!("user{}", i));
+    }
+    store.set("user:x:email".to_owned(), "bad id".to_owned());
+
+    let emails = store.scan_regex(r"^user:\d+:email$", None).unwrap();
+    assert_eq!(emails.len(), 10000);
+    assert!(emails.iter().all(|(k, _)| k.ends_with(":email")));
+
+    let limited = store.scan_regex(r"^user:1\d*:name$", Some(5)).unwrap();
+    assert_eq!(limited.len(), 5);
+    // keys come back in lexicographic order, so user:1000 sorts before user:1
+    assert_eq!(
+        limited[0],
+        ("user:1000:name".to_owned(), "user1000".to_owned())
+    );
+
+    assert!(matches!(
+        store.scan_regex("user:(", None),
+        Err(KvsError::InvalidPattern(_))
+    ));
+}
+
+#[test]
+fn transient_errors() {
+    let timed_out = KvsError::Io(io::Error::new(io::ErrorKind::TimedOut, "slow"));
+    assert!(timed_out.is_transient());
+    let refused = KvsError::Io(io::Error::new(io::ErrorKind::ConnectionRefused, "down"));
+    assert!(refused.is_transient());
+
+    let not_found = KvsError::Io(io::Error::new(io::ErrorKind::NotFound, "gone"));
+    assert!(!not_found.is_transient());
+    assert!(!KvsError::KeyNotFound("key1".to_owned()).is_transient());
+    assert!(!KvsError::InvalidValueType.is_transient());
+    assert!(!KvsError::Overflow.is_transient());
+}
+
+#[test]
+fn set_with_huge_ttl_never_expires() {
+    let mut store = KvStore::new();
+    store.set_with_ttl("key1".to_owned(), "value1".to_owned(), Duration::MAX);
+    assert_eq!(store.get("key1".to_owned()).unwrap(), "value1".to_owned());
+    assert_eq!(store.remove_expired(), 0);
+    assert_eq!(store.len(), 1);
+}